@@ -0,0 +1,137 @@
+//! Fixed-capacity, allocation-free counterpart to [`crate::DataString`] for
+//! `no_std` targets (gated behind the `fixed-capacity` feature). Byte storage
+//! and the replacements side table both live on the stack, bounded by the
+//! const generics `N` (byte capacity) and `M` (maximum number of non-ASCII
+//! bytes that can be recorded).
+
+use core::fmt::Display;
+use heapless::Vec as HVec;
+
+use crate::{ASCII_MAX, SUBSTITUTE};
+
+/// Returned when input bytes (or the number of non-ASCII bytes within them)
+/// exceed the fixed capacity of a [`DataStringFixed`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CapacityError;
+
+impl Display for CapacityError {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        write!(f, "input exceeds fixed DataString capacity")
+    }
+}
+
+/// Fixed-capacity, `no_std` version of [`crate::DataString`]. `N` bounds the
+/// byte buffer; `M` bounds the number of non-ASCII bytes that can be
+/// recorded for reconstitution. The public surface intentionally mirrors
+/// `DataString` so code can be generic over both the heap and fixed-capacity
+/// forms.
+#[derive(Debug, PartialEq)]
+pub struct DataStringFixed<const N: usize, const M: usize> {
+    string_rep: Option<HVec<u8, N>>,
+    replacements: HVec<(usize, u8), M>
+}
+
+impl<const N: usize, const M: usize> DataStringFixed<N, M> {
+    pub fn from_vec(mut data: HVec<u8, N>) -> Result<Self, CapacityError> {
+        let mut replacements: HVec<(usize, u8), M> = HVec::new();
+        for (index, value) in data.iter_mut().enumerate() {
+            if *value > ASCII_MAX {
+                replacements.push((index, *value)).map_err(|_| CapacityError)?;
+                *value = SUBSTITUTE;
+            }
+        }
+        Ok(DataStringFixed {
+            string_rep: Some(data),
+            replacements
+        })
+    }
+
+    pub fn from_slice(data: &[u8]) -> Result<Self, CapacityError> {
+        let mut buf: HVec<u8, N> = HVec::new();
+        buf.extend_from_slice(data).map_err(|_| CapacityError)?;
+        Self::from_vec(buf)
+    }
+
+    pub fn take_data(&mut self) -> Option<HVec<u8, N>> {
+        let mut data = self.string_rep.take()?;
+        for (index, saved_byte) in self.replacements.iter() {
+            data[*index] = *saved_byte;
+        }
+        Some(data)
+    }
+
+    pub fn return_data_unchecked(&mut self, mut data: HVec<u8, N>) -> Option<HVec<u8, N>> {
+        if self.string_rep.is_some() { return Some(data) };
+        for (index, _) in self.replacements.iter() {
+            data[*index] = SUBSTITUTE;
+        }
+        self.string_rep = Some(data);
+        None
+    }
+}
+
+impl<const N: usize, const M: usize> AsRef<str> for DataStringFixed<N, M> {
+    fn as_ref(&self) -> &str {
+        match self.string_rep {
+            None => "",
+            Some(ref data) => core::str::from_utf8(data).unwrap_or("")
+        }
+    }
+}
+
+impl<const N: usize, const M: usize> AsRef<[u8]> for DataStringFixed<N, M> {
+    fn as_ref(&self) -> &[u8] {
+        match self.string_rep {
+            None => &[],
+            Some(ref data) => data.as_slice()
+        }
+    }
+}
+
+impl<const N: usize, const M: usize> Display for DataStringFixed<N, M> {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        let s: &str = self.as_ref();
+        for c in s.chars() {
+            if c != SUBSTITUTE as char {
+                write!(f, "{}", c)?;
+            } else {
+                write!(f, "�")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn get_test_buf() -> HVec<u8, 32> {
+        let mut data: HVec<u8, 32> = HVec::new();
+        data.extend_from_slice(b"Want binary? data: ").unwrap();
+        data.extend_from_slice(&[128, 199, 200, 255]).unwrap();
+        data
+    }
+
+    #[test]
+    fn basic_round_trip() {
+        let data = get_test_buf();
+        let data_copy = data.clone();
+        let mut d_string: DataStringFixed<32, 8> = DataStringFixed::from_vec(data).unwrap();
+        assert_eq!(d_string.take_data(), Some(data_copy));
+    }
+
+    #[test]
+    fn from_slice_over_capacity_errors() {
+        let data = [0u8; 8];
+        let result = DataStringFixed::<4, 4>::from_slice(&data);
+        assert_eq!(result, Err(CapacityError));
+    }
+
+    #[test]
+    fn too_many_non_ascii_bytes_errors() {
+        let data = [200u8; 4];
+        let result = DataStringFixed::<4, 2>::from_slice(&data);
+        assert_eq!(result, Err(CapacityError));
+    }
+}
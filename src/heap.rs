@@ -0,0 +1,718 @@
+use std::collections::HashMap;
+use std::convert::AsRef;
+use std::fmt::Display;
+
+use crate::{ASCII_MAX, SUBSTITUTE};
+
+
+/// Error returned by `DataString::from_escaped_string` when the input does
+/// not follow the `\xHH` / `\\` escape grammar produced by `to_escaped_string`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParseError {
+    /// A `\` appeared with no following `\` or `xHH` escape.
+    DanglingBackslash,
+    /// A `\x` escape was not followed by exactly two hex digits.
+    InvalidHexEscape,
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            ParseError::DanglingBackslash => write!(f, "backslash not followed by a `\\` or `x` escape"),
+            ParseError::InvalidHexEscape => write!(f, "\\x escape not followed by two hex digits"),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// Returned by the `push_byte`/`push_slice`/`Write` append API when the
+/// string rep has been taken (via `take_string`/`take_data`) and not yet
+/// returned, so there is nowhere to append to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BufferTakenError;
+
+impl Display for BufferTakenError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "DataString string rep is currently taken")
+    }
+}
+
+impl std::error::Error for BufferTakenError {}
+
+/// Error returned by `DataString::return_data`: either there is no
+/// `take_string` snapshot to reconcile against, or a length-changing edit
+/// moved or removed a substitution marker so its anchor run could no longer
+/// be found, in order, in the edited buffer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReconstitutionError {
+    /// `return_data` was called without a prior `take_string` snapshot to
+    /// reconcile against.
+    NotBorrowed,
+    /// A substitution's anchor run (and the marker byte following it)
+    /// could not be located, in order, past the previous match.
+    LostMarker,
+}
+
+impl Display for ReconstitutionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            ReconstitutionError::NotBorrowed => write!(f, "return_data called without a take_string snapshot to reconcile against"),
+            ReconstitutionError::LostMarker => write!(f, "a substitution marker could not be relocated in the edited buffer"),
+        }
+    }
+}
+
+impl std::error::Error for ReconstitutionError {}
+
+/// For each substitution, in index order: the run of ASCII bytes that
+/// preceded it (since the previous substitution, or buffer start) and the
+/// original byte it stands in for.
+type Anchors = Vec<(Vec<u8>, u8)>;
+
+#[derive(Debug, PartialEq)]
+pub struct DataString {
+    string_rep: Option<String>,
+    replacements: HashMap<u8, Vec<usize>>,
+    /// Snapshot taken by `take_string`: the original byte length plus the
+    /// anchor list. Lets `return_data` splice preserved bytes back even
+    /// after a length-changing edit to the borrowed string has shifted
+    /// every index past that point.
+    anchors: Option<(usize, Anchors)>
+}
+
+impl DataString {
+    pub fn from_vec(mut data: Vec<u8>) -> Self {
+        let mut replacements: HashMap<u8, Vec<usize>> = HashMap::new();
+        for (index, value) in data.iter_mut().enumerate() {
+            if *value > ASCII_MAX {
+                replacements.entry(*value).or_default().push(index);
+                *value = SUBSTITUTE;
+            }
+        }
+        for value in data.iter() {
+            debug_assert!(*value <= ASCII_MAX);
+        }
+        unsafe {
+            DataString {
+            string_rep: Some(String::from_utf8_unchecked(data)),
+            replacements,
+            anchors: None
+            }
+        }
+    }
+    /// Like `from_vec`, but preserves valid UTF-8 sequences instead of
+    /// substituting every byte above `ASCII_MAX`. Only bytes that are
+    /// genuinely invalid UTF-8 are recorded in `replacements` and replaced
+    /// with `SUBSTITUTE`, so text that happens to already be valid UTF-8
+    /// (with some garbage mixed in) round-trips through `Display` as
+    /// readable Unicode rather than a row of `�`.
+    pub fn from_vec_utf8_lossless(mut data: Vec<u8>) -> Self {
+        let mut replacements: HashMap<u8, Vec<usize>> = HashMap::new();
+        let mut start = 0;
+        while start < data.len() {
+            match std::str::from_utf8(&data[start..]) {
+                Ok(_) => break,
+                Err(e) => {
+                    let invalid_start = start + e.valid_up_to();
+                    let invalid_len = match e.error_len() {
+                        Some(n) => n,
+                        None => data.len() - invalid_start,
+                    };
+                    for (index, value) in data.iter_mut().enumerate().skip(invalid_start).take(invalid_len) {
+                        replacements.entry(*value).or_default().push(index);
+                        *value = SUBSTITUTE;
+                    }
+                    start = invalid_start + invalid_len;
+                }
+            }
+        }
+        unsafe {
+            DataString {
+                string_rep: Some(String::from_utf8_unchecked(data)),
+                replacements,
+                anchors: None
+            }
+        }
+    }
+    pub fn take_string(&mut self) -> Option<String> {
+        if let Some(ref s) = self.string_rep {
+            self.anchors = Some((s.len(), Self::compute_anchors(s, &self.replacements)));
+        }
+        self.string_rep.take()
+    }
+    pub fn return_string(&mut self, s: String) -> Option<String> {
+        match self.string_rep {
+            None => {
+                self.string_rep = Some(s);
+                None
+            },
+            Some(_) => Some(s)
+        }
+    }
+    pub fn take_data(&mut self) -> Option<Vec<u8>> {
+        self.string_rep.as_ref()?;
+        let mut data = self.string_rep.take().unwrap().into_bytes();
+        for (saved_data, index_vec) in self.replacements.iter() {
+            for index in index_vec.iter() {
+                data[*index] = *saved_data;
+            }
+        };
+        Some(data)
+    }
+    pub fn return_data_unchecked(&mut self, mut data: Vec<u8>) -> Option<Vec<u8>> {
+        if self.string_rep.is_some() { return Some(data) };
+        for index_vec in self.replacements.values() {
+            for index in index_vec {
+                data[*index] = SUBSTITUTE;
+            }
+        }
+        self.string_rep = Some(String::from_utf8(data).unwrap());
+        None
+    }
+    /// Builds the ordered (anchor run, preserved byte) list used by
+    /// `return_data`: for each substitution, in index order, the ASCII
+    /// bytes since the previous substitution (or buffer start) and the
+    /// original byte it replaced.
+    fn compute_anchors(ascii: &str, replacements: &HashMap<u8, Vec<usize>>) -> Anchors {
+        let mut ordered: Vec<(usize, u8)> = replacements.iter()
+            .flat_map(|(byte, indices)| indices.iter().map(move |index| (*index, *byte)))
+            .collect();
+        ordered.sort_by_key(|(index, _)| *index);
+        let bytes = ascii.as_bytes();
+        let mut cursor = 0;
+        ordered.into_iter().map(|(index, original_byte)| {
+            let anchor = bytes[cursor..index].to_vec();
+            cursor = index + 1;
+            (anchor, original_byte)
+        }).collect()
+    }
+    /// Finds `anchor` immediately followed by `SUBSTITUTE`, searching
+    /// `edited[cursor..]` left to right.
+    fn find_anchor(edited: &[u8], cursor: usize, anchor: &[u8]) -> Option<usize> {
+        let needle_len = anchor.len() + 1;
+        if cursor + needle_len > edited.len() { return None };
+        (cursor..=edited.len() - needle_len).find(|&start| {
+            &edited[start..start + anchor.len()] == anchor && edited[start + anchor.len()] == SUBSTITUTE
+        })
+    }
+    /// Reconciles `edited` — a transformation of the `String` most recently
+    /// returned by `take_string` — against the snapshot that call took,
+    /// splicing the preserved non-ASCII bytes back in even if the edit
+    /// changed the buffer's length and shifted every later substitution.
+    /// On success the reconstituted bytes both are returned *and* become
+    /// this `DataString`'s new contents (re-substituted, ready for further
+    /// `take_string`/`take_data` calls) — mirroring how `return_data_unchecked`
+    /// puts the object back into a usable state rather than leaving it
+    /// emptied out. On error (including a missing snapshot) the object is
+    /// left untouched so the call can be retried.
+    ///
+    /// When `edited.len()` matches the original length, this takes the fast
+    /// index-based path (substitution positions are trusted to be stable).
+    /// Otherwise it re-scans `edited` for each substitution's anchor run, in
+    /// order, and reports `ReconstitutionError::LostMarker` rather than
+    /// corrupting output if one can't be found.
+    pub fn return_data(&mut self, edited: Vec<u8>) -> Result<Vec<u8>, ReconstitutionError> {
+        let (original_len, anchors) = match self.anchors {
+            Some((original_len, ref anchors)) => (original_len, anchors),
+            None => return Err(ReconstitutionError::NotBorrowed)
+        };
+        let data = if edited.len() == original_len {
+            let mut data = edited;
+            for (saved_byte, index_vec) in self.replacements.iter() {
+                for index in index_vec.iter() {
+                    data[*index] = *saved_byte;
+                }
+            }
+            data
+        } else {
+            let mut output = Vec::with_capacity(edited.len());
+            let mut cursor = 0;
+            for (anchor, preserved_byte) in anchors.iter() {
+                match Self::find_anchor(&edited, cursor, anchor) {
+                    Some(start) => {
+                        output.extend_from_slice(&edited[cursor..start]);
+                        output.extend_from_slice(anchor);
+                        output.push(*preserved_byte);
+                        cursor = start + anchor.len() + 1;
+                    },
+                    None => return Err(ReconstitutionError::LostMarker)
+                }
+            }
+            output.extend_from_slice(&edited[cursor..]);
+            output
+        };
+        *self = DataString::from_vec(data.clone());
+        Ok(data)
+    }
+    /// Scoped borrow of the string rep: lets the caller read `&str` without
+    /// manually juggling `take_string`/`return_string`.
+    pub fn with_str<R>(&self, f: impl FnOnce(&str) -> R) -> R {
+        f(self.as_ref())
+    }
+    /// Scoped borrow of the original bytes: takes the buffer, hands it to
+    /// `f`, then rebuilds the string rep and `replacements` from scratch
+    /// (as `from_vec` would) before returning, so the buffer can never be
+    /// left taken by mistake and `f` is free to introduce new non-ASCII
+    /// bytes anywhere, not just at previously-recorded indices.
+    pub fn with_data<R>(&mut self, f: impl FnOnce(&mut Vec<u8>) -> R) -> R {
+        let mut data = self.take_data().unwrap_or_default();
+        let result = f(&mut data);
+        *self = DataString::from_vec(data);
+        result
+    }
+    /// Reconstitutes the original bytes without consuming the string rep,
+    /// for use by read-only views like `to_escaped_string`.
+    fn reconstituted_bytes(&self) -> Vec<u8> {
+        let mut data = match self.string_rep {
+            None => return Vec::new(),
+            Some(ref s) => s.as_bytes().to_vec()
+        };
+        for (saved_data, index_vec) in self.replacements.iter() {
+            for index in index_vec.iter() {
+                data[*index] = *saved_data;
+            }
+        }
+        data
+    }
+    /// Serializes the original bytes as a single printable-ASCII string:
+    /// every non-ASCII byte, control byte (`< 0x20` or `0x7F`), and literal
+    /// backslash is emitted as a `\xHH` hex escape (or `\\` for the
+    /// backslash); everything else passes through verbatim. The result can
+    /// be embedded in JSON/logs/config and parsed back losslessly with
+    /// `from_escaped_string`.
+    pub fn to_escaped_string(&self) -> String {
+        let data = self.reconstituted_bytes();
+        let mut escaped = String::with_capacity(data.len());
+        for byte in data {
+            match byte {
+                b'\\' => escaped.push_str("\\\\"),
+                b if !(0x20..=ASCII_MAX).contains(&b) || b == 0x7F => escaped.push_str(&format!("\\x{:02X}", b)),
+                b => escaped.push(b as char)
+            }
+        }
+        escaped
+    }
+    /// Parses the grammar produced by `to_escaped_string` back into the
+    /// exact original bytes and builds a `DataString` from them.
+    pub fn from_escaped_string(s: &str) -> Result<DataString, ParseError> {
+        let bytes = s.as_bytes();
+        let mut data = Vec::with_capacity(bytes.len());
+        let mut index = 0;
+        while index < bytes.len() {
+            match bytes[index] {
+                b'\\' => match bytes.get(index + 1) {
+                    Some(b'\\') => {
+                        data.push(b'\\');
+                        index += 2;
+                    },
+                    Some(b'x') => {
+                        let hex = bytes.get(index + 2..index + 4).ok_or(ParseError::InvalidHexEscape)?;
+                        if !hex.iter().all(|b| b.is_ascii_hexdigit()) {
+                            return Err(ParseError::InvalidHexEscape);
+                        }
+                        let hex = std::str::from_utf8(hex).map_err(|_| ParseError::InvalidHexEscape)?;
+                        let value = u8::from_str_radix(hex, 16).map_err(|_| ParseError::InvalidHexEscape)?;
+                        data.push(value);
+                        index += 4;
+                    },
+                    _ => return Err(ParseError::DanglingBackslash)
+                },
+                b => {
+                    data.push(b);
+                    index += 1;
+                }
+            }
+        }
+        Ok(DataString::from_vec(data))
+    }
+    /// Appends a single byte, substituting and recording it at its
+    /// post-substitution index if it's non-ASCII. Only valid while the
+    /// string rep is present.
+    pub fn push_byte(&mut self, byte: u8) -> Result<(), BufferTakenError> {
+        let index = match self.string_rep {
+            Some(ref s) => s.len(),
+            None => return Err(BufferTakenError)
+        };
+        if byte > ASCII_MAX {
+            self.replacements.entry(byte).or_default().push(index);
+            self.string_rep.as_mut().unwrap().push(SUBSTITUTE as char);
+        } else {
+            self.string_rep.as_mut().unwrap().push(byte as char);
+        }
+        Ok(())
+    }
+    /// Appends each byte of `data` via `push_byte`.
+    pub fn push_slice(&mut self, data: &[u8]) -> Result<(), BufferTakenError> {
+        for byte in data {
+            self.push_byte(*byte)?;
+        }
+        Ok(())
+    }
+}
+
+impl AsRef<str> for DataString {
+    fn as_ref(&self) -> &str {
+        match self.string_rep {
+            None => "",
+            Some(ref s) => s
+        }
+    }
+}
+
+impl AsRef<[u8]> for DataString {
+    fn as_ref(&self) -> &[u8] {
+        match self.string_rep {
+            None => &[],
+            Some(ref s) => s.as_ref()
+        }
+    }
+}
+
+impl Display for DataString {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self.string_rep {
+            None => write!(f, "")?,
+            Some(ref s) => {
+                for c in s.chars() {
+                    if c != SUBSTITUTE as char {
+                        write!(f, "{}", c)?;
+                    } else {
+                        write!(f, "�")?;
+                    };
+                };
+            }
+        }
+        Ok(())
+    }
+}
+
+impl std::ops::Deref for DataString {
+    type Target = str;
+    fn deref(&self) -> &str {
+        self.as_ref()
+    }
+}
+
+impl<I: std::slice::SliceIndex<str>> std::ops::Index<I> for DataString {
+    type Output = I::Output;
+    fn index(&self, index: I) -> &Self::Output {
+        std::ops::Index::index(&**self, index)
+    }
+}
+
+impl Extend<u8> for DataString {
+    fn extend<T: IntoIterator<Item = u8>>(&mut self, iter: T) {
+        for byte in iter {
+            self.push_byte(byte).expect("DataString::extend called while the string rep was taken");
+        }
+    }
+}
+
+impl FromIterator<u8> for DataString {
+    fn from_iter<T: IntoIterator<Item = u8>>(iter: T) -> Self {
+        let mut d_string = DataString::from_vec(Vec::new());
+        d_string.extend(iter);
+        d_string
+    }
+}
+
+impl std::io::Write for DataString {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.push_slice(buf).map_err(std::io::Error::other)?;
+        Ok(buf.len())
+    }
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fmt::Write;
+    use super::*;
+
+    const TEST_SLICE: &[u8; 30] = b"Want binary? Here's data: \x1A\x1A\x1A\x1A";
+
+    fn get_test_data() -> Vec<u8> {
+        let mut data: Vec<u8> = b"Want binary? Here's data: ".to_vec();
+        data.extend(vec!(128, 199, 200, 255));
+        data
+    }
+
+    fn get_string_equivalent() -> String {
+        "Want binary? Here's data: \x1A\x1A\x1A\x1A".to_string()
+    }
+
+    fn get_expected_display_string() -> String {
+        "Want binary? Here's data: ����".to_string()
+    }
+
+    #[test]
+    fn basic_test() {
+        let data = get_test_data();
+        let data_copy = data.clone();
+        let mut d_string = DataString::from_vec(data);
+        let mut test_output = String::new();
+        write!(test_output, "{}", d_string).unwrap();
+        assert_eq!(test_output, get_expected_display_string());
+        let data_ref: &[u8] = d_string.as_ref();
+        let string_ref: &str = d_string.as_ref();
+        assert_eq!(data_ref, TEST_SLICE);
+        assert_eq!(string_ref, &get_string_equivalent());
+        let reconstituted_data = d_string.take_data();
+        assert_eq!(Some(data_copy), reconstituted_data);
+    }
+
+    #[test]
+    fn no_double_borrows() {
+        let data = get_test_data();
+        let mut d_string = DataString::from_vec(data);
+        assert_eq!(d_string.take_string(), Some(get_string_equivalent()));
+        assert_eq!(d_string.take_string(), None);
+        assert_eq!(d_string.take_data(), None);
+    }
+
+    #[test]
+    fn no_double_returns() {
+        let data = get_test_data();
+        let mut d_string = DataString::from_vec(data);
+        let new_data = vec!(1, 2, 3);
+        let copied_new_data = new_data.clone();
+        assert_eq!(d_string.return_data_unchecked(new_data), Some(copied_new_data));
+        let new_string = "test".to_string();
+        let copied_new_string = new_string.clone();
+        assert_eq!(d_string.return_string(new_string), Some(copied_new_string));
+    }
+
+    #[test]
+    fn data_borrow_works() {
+        let data = get_test_data();
+        let mut d_string = DataString::from_vec(data);
+        let borrowed_data = d_string.take_data();
+        assert_eq!(borrowed_data, Some(get_test_data()));
+        assert_eq!(d_string.return_data_unchecked(borrowed_data.unwrap()), None);
+        assert_eq!(d_string.take_data().unwrap(), get_test_data());
+    }
+
+    #[test]
+    fn string_borrow_works() {
+        let data = get_test_data();
+        let mut d_string = DataString::from_vec(data);
+        let borrowed_string = d_string.take_string();
+        assert_eq!(borrowed_string, Some(get_string_equivalent()));
+        assert_eq!(d_string.return_string(borrowed_string.unwrap()), None);
+        assert_eq!(d_string.take_data().unwrap(), get_test_data());
+    }
+
+    #[test]
+    #[should_panic(expected = "called `Result::unwrap()` on an `Err` value: FromUtf8Error")]
+    fn panic_if_data_modified() {
+        let data = vec!(b'a', 200, b'g');
+        let mut d_string = DataString::from_vec(data);
+        let mut borrowed_data = d_string.take_data().unwrap();
+        // Introducing invalid character at a new place
+        borrowed_data[0] = 200;
+        d_string.return_data_unchecked(borrowed_data);
+    }
+
+    #[test]
+    fn lossless_preserves_valid_utf8() {
+        let data: Vec<u8> = "Héllo wörld".bytes().collect();
+        let data_copy = data.clone();
+        let mut d_string = DataString::from_vec_utf8_lossless(data);
+        let mut test_output = String::new();
+        write!(test_output, "{}", d_string).unwrap();
+        assert_eq!(test_output, "Héllo wörld");
+        assert_eq!(d_string.take_data(), Some(data_copy));
+    }
+
+    #[test]
+    fn lossless_substitutes_only_invalid_bytes() {
+        let mut data: Vec<u8> = "Héllo".bytes().collect();
+        data.push(0xFF); // not valid UTF-8 on its own
+        data.extend("wörld".bytes());
+        let data_copy = data.clone();
+        let mut d_string = DataString::from_vec_utf8_lossless(data);
+        let mut test_output = String::new();
+        write!(test_output, "{}", d_string).unwrap();
+        assert_eq!(test_output, "Héllo�wörld");
+        assert_eq!(d_string.take_data(), Some(data_copy));
+    }
+
+    #[test]
+    fn lossless_handles_truncated_trailing_sequence() {
+        let mut data: Vec<u8> = b"abc".to_vec();
+        data.push(0xE2); // start of a 3-byte sequence that never completes
+        let data_copy = data.clone();
+        let mut d_string = DataString::from_vec_utf8_lossless(data);
+        let mut test_output = String::new();
+        write!(test_output, "{}", d_string).unwrap();
+        assert_eq!(test_output, "abc�");
+        assert_eq!(d_string.take_data(), Some(data_copy));
+    }
+
+    #[test]
+    fn escaped_string_round_trips() {
+        let data = get_test_data();
+        let d_string = DataString::from_vec(data.clone());
+        let escaped = d_string.to_escaped_string();
+        assert_eq!(escaped, "Want binary? Here's data: \\x80\\xC7\\xC8\\xFF");
+        let mut restored = DataString::from_escaped_string(&escaped).unwrap();
+        assert_eq!(restored.take_data(), Some(data));
+    }
+
+    #[test]
+    fn escaped_string_escapes_literal_backslash() {
+        let data = b"a\\b".to_vec();
+        let data_copy = data.clone();
+        let d_string = DataString::from_vec(data);
+        let escaped = d_string.to_escaped_string();
+        assert_eq!(escaped, "a\\\\b");
+        let mut restored = DataString::from_escaped_string(&escaped).unwrap();
+        assert_eq!(restored.take_data(), Some(data_copy));
+    }
+
+    #[test]
+    fn from_escaped_string_rejects_dangling_backslash() {
+        assert_eq!(DataString::from_escaped_string("abc\\"), Err(ParseError::DanglingBackslash));
+    }
+
+    #[test]
+    fn from_escaped_string_rejects_bad_hex_escape() {
+        assert_eq!(DataString::from_escaped_string("\\xZZ"), Err(ParseError::InvalidHexEscape));
+        assert_eq!(DataString::from_escaped_string("\\x1"), Err(ParseError::InvalidHexEscape));
+    }
+
+    #[test]
+    fn from_escaped_string_rejects_signed_hex_escape() {
+        // `u8::from_str_radix` alone would accept a leading '+' and parse this as 0x01.
+        assert_eq!(DataString::from_escaped_string("\\x+1"), Err(ParseError::InvalidHexEscape));
+    }
+
+    #[test]
+    fn escaped_string_escapes_control_bytes() {
+        let data = vec![0x1A, 0x07];
+        let data_copy = data.clone();
+        let d_string = DataString::from_vec(data);
+        let escaped = d_string.to_escaped_string();
+        assert_eq!(escaped, "\\x1A\\x07");
+        let mut restored = DataString::from_escaped_string(&escaped).unwrap();
+        assert_eq!(restored.take_data(), Some(data_copy));
+    }
+
+    #[test]
+    fn deref_exposes_str_methods() {
+        let d_string = DataString::from_vec(get_test_data());
+        assert_eq!(d_string.len(), get_string_equivalent().len());
+        assert!(d_string.starts_with("Want binary?"));
+        assert_eq!(&d_string[0..4], "Want");
+    }
+
+    #[test]
+    fn with_str_reads_without_taking() {
+        let d_string = DataString::from_vec(get_test_data());
+        let first_word = d_string.with_str(|s| s.split_whitespace().next().unwrap().to_string());
+        assert_eq!(first_word, "Want");
+        // string rep is still present afterwards
+        assert_eq!(d_string.as_ref() as &str, get_string_equivalent());
+    }
+
+    #[test]
+    fn with_data_round_trips_and_puts_buffer_back() {
+        let data = get_test_data();
+        let mut d_string = DataString::from_vec(data);
+        let len = d_string.with_data(|data| data.len());
+        assert_eq!(len, TEST_SLICE.len());
+        // buffer was put back, so a further take_data still reconstitutes exactly
+        assert_eq!(d_string.take_data(), Some(get_test_data()));
+    }
+
+    #[test]
+    fn with_data_handles_new_non_ascii_bytes_from_the_closure() {
+        let mut d_string = DataString::from_vec(b"abc".to_vec());
+        d_string.with_data(|data| data.push(200));
+        assert_eq!(d_string.take_data(), Some(vec!(b'a', b'b', b'c', 200)));
+    }
+
+    #[test]
+    fn push_byte_and_push_slice_build_incrementally() {
+        let mut d_string = DataString::from_vec(Vec::new());
+        for byte in b"abc" {
+            d_string.push_byte(*byte).unwrap();
+        }
+        d_string.push_slice(&[200, 255]).unwrap();
+        assert_eq!(d_string.take_data(), Some(vec!(b'a', b'b', b'c', 200, 255)));
+    }
+
+    #[test]
+    fn push_byte_errors_once_string_rep_is_taken() {
+        let mut d_string = DataString::from_vec(get_test_data());
+        d_string.take_string();
+        assert_eq!(d_string.push_byte(b'a'), Err(BufferTakenError));
+    }
+
+    #[test]
+    fn from_iterator_and_extend_match_from_vec() {
+        let data = get_test_data();
+        let mut built: DataString = data.iter().copied().collect();
+        built.extend(Vec::new()); // no-op extend still works afterwards
+        assert_eq!(built.take_data(), Some(data));
+    }
+
+    #[test]
+    fn write_impl_appends_bytes() {
+        use std::io::Write;
+        let mut d_string = DataString::from_vec(Vec::new());
+        write!(d_string, "ok: {}", 199u8).unwrap();
+        assert_eq!(d_string.take_data(), Some(b"ok: 199".to_vec()));
+    }
+
+    #[test]
+    fn return_data_fast_path_same_length_edit() {
+        let mut d_string = DataString::from_vec(get_test_data());
+        let mut s = d_string.take_string().unwrap();
+        s.replace_range(0..1, "w"); // same-length edit, markers don't move
+        let result = d_string.return_data(s.into_bytes()).unwrap();
+        let mut expected = get_test_data();
+        expected[0] = b'w';
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn return_data_anchor_path_survives_length_change() {
+        let mut d_string = DataString::from_vec(get_test_data());
+        let s = d_string.take_string().unwrap();
+        // Prepending text shifts every substitution's absolute index without
+        // touching the anchor runs themselves, so the anchor walk relocates them.
+        let edited = format!("Hello! {}", s);
+        let result = d_string.return_data(edited.into_bytes()).unwrap();
+        let mut expected: Vec<u8> = b"Hello! Want binary? Here's data: ".to_vec();
+        expected.extend_from_slice(&[128, 199, 200, 255]);
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn return_data_errors_without_a_snapshot() {
+        let mut d_string = DataString::from_vec(get_test_data());
+        assert_eq!(d_string.return_data(vec!(1, 2, 3)), Err(ReconstitutionError::NotBorrowed));
+    }
+
+    #[test]
+    fn return_data_reports_lost_marker() {
+        let mut d_string = DataString::from_vec(get_test_data());
+        let s = d_string.take_string().unwrap();
+        let edited = s.replace('\u{1a}', ""); // drops every substitution marker
+        assert_eq!(d_string.return_data(edited.into_bytes()), Err(ReconstitutionError::LostMarker));
+    }
+
+    #[test]
+    fn return_data_leaves_self_usable_after_success() {
+        let mut d_string = DataString::from_vec(get_test_data());
+        let s = d_string.take_string().unwrap();
+        let edited = format!("Hello! {}", s);
+        let result = d_string.return_data(edited.into_bytes()).unwrap();
+        // `self` must be restored to a fresh, usable `DataString`, not left empty.
+        assert_eq!(d_string.take_data(), Some(result));
+    }
+}